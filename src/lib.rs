@@ -1,103 +1,278 @@
-use core::cell::RefCell;
-use defmt::*;
-use embassy_stm32::i2c::{Error, I2c}; // Use Async explicitly
-use embassy_stm32::mode::Async;
-use embassy_time::{Duration, Timer};
+use defmt::{error, info, warn};
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::i2c::I2c;
 
 // These might be in your main.rs or lib.rs, ensure they are accessible
 // use defmt_rtt as _;
 // use panic_probe as _;
 
-const AHT20_ADDRESS: u8 = 0x38; // AHT20 sensor I2C address
-const CMD_INITIALIZE: [u8; 3] = [0xBE, 0x08, 0x00]; // Initialization command with calibration enable
+const AHT20_ADDRESS: u8 = 0x38; // Shared I2C address for both the AHT20 and the AHT10
+const CMD_INITIALIZE_AHT20: [u8; 3] = [0xBE, 0x08, 0x00]; // AHT20 initialization command with calibration enable
+const CMD_INITIALIZE_AHT10: [u8; 3] = [0xE1, 0x08, 0x00]; // AHT10 initialization command with calibration enable
 const CMD_TRIGGER_MEASUREMENT: [u8; 3] = [0xAC, 0x33, 0x00]; // Trigger measurement command
-// const CMD_SOFT_RESET: [u8;1] = [0xBA]; // Soft reset command (optional)
+const CMD_CHECK_STATUS: [u8; 1] = [0x71]; // Reads back the single status byte
+const CMD_SOFT_RESET: [u8; 1] = [0xBA]; // Soft reset command
 
 // Delays from datasheet
-const DELAY_POWER_ON_MS: u64 = 40; // Sensor needs >20ms after power on, recommend 40ms.
-const DELAY_INIT_CALIBRATION_MS: u64 = 80; // Wait for calibration after 0xBE, 0x08, 0x00. Datasheet: 50-80ms.
-const DELAY_MEASUREMENT_MS: u64 = 80; // Wait for measurement to complete. Datasheet: >75ms.
+const DELAY_SOFT_RESET_MS: u32 = 30; // Sensor needs 20-40ms to recover after a soft reset.
+
+// Default busy-bit polling configuration, tunable per-instance via `new_with_polling()`.
+const DEFAULT_MAX_POLL_ATTEMPTS: u8 = 10;
+const DEFAULT_POLL_INTERVAL_MS: u32 = 10; // Datasheet recommends checking every ~10ms.
 
 #[derive(Debug)]
-pub enum Aht20Error {
-    I2c(Error),
+pub enum Aht20Error<E> {
+    I2c(E),
     NotInitialized,
     MeasurementBusy,
     NotCalibrated,
-    // CrcError, // Can be added if CRC check is implemented
+    CrcError,
+}
+
+/// The driver's view of the sensor's calibration state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum SensorState {
+    /// `init()` has not yet run (or a soft reset cleared it) and the sensor's calibration
+    /// status is unknown.
+    Uninitialized,
+    /// The sensor reported its CalEnable bit set; readings should be trustworthy.
+    Calibrated,
+    /// The sensor reported its CalEnable bit clear, either after the initialize command or in
+    /// a measurement frame.
+    Faulted,
+}
+
+/// Selects which Aosong sensor part is on the bus. Both parts share the same I2C address,
+/// measurement command, and data layout; they differ only in the initialize opcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorFamily {
+    Aht10,
+    Aht20,
+}
+
+impl SensorFamily {
+    fn init_command(self) -> [u8; 3] {
+        match self {
+            SensorFamily::Aht10 => CMD_INITIALIZE_AHT10,
+            SensorFamily::Aht20 => CMD_INITIALIZE_AHT20,
+        }
+    }
+}
+
+/// A single humidity/temperature measurement, along with the raw 20-bit sensor counts it was
+/// derived from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    pub temperature_celsius: f32,
+    pub humidity_percent: f32,
+    /// The raw 20-bit humidity count (S_RH in the datasheet), for callers that want to do
+    /// their own calibration or logging.
+    pub raw_humidity: u32,
+    /// The raw 20-bit temperature count (S_T in the datasheet).
+    pub raw_temperature: u32,
+}
+
+impl Measurement {
+    /// Converts `temperature_celsius` to Fahrenheit.
+    pub fn temperature_fahrenheit(&self) -> f32 {
+        self.temperature_celsius * 9.0 / 5.0 + 32.0
+    }
+
+    /// Computes the dew point in Celsius using the Magnus formula.
+    pub fn dew_point_celsius(&self) -> f32 {
+        const A: f32 = 17.62;
+        const B: f32 = 243.12;
+        let gamma = (A * self.temperature_celsius) / (B + self.temperature_celsius)
+            + libm::logf(self.humidity_percent / 100.0);
+        (B * gamma) / (A - gamma)
+    }
 }
 
-impl From<Error> for Aht20Error {
-    fn from(e: Error) -> Self {
-        Aht20Error::I2c(e)
+/// Computes the AHT20's CRC-8 checksum (poly 0x31, init 0xFF, no reflection, no final XOR)
+/// over the given bytes.
+fn crc8(bytes: &[u8]) -> u8 {
+    let mut crc: u8 = 0xFF;
+    for &byte in bytes {
+        crc ^= byte;
+        for _ in 0..8 {
+            if (crc & 0x80) != 0 {
+                crc = (crc << 1) ^ 0x31;
+            } else {
+                crc <<= 1;
+            }
+        }
     }
+    crc
 }
 
-pub struct AHT20<'a> {
-    i2c: &'a RefCell<I2c<'static, Async>>,
-    initialized: bool, // Track initialization state
+pub struct AHT20<I2C, D> {
+    i2c: I2C,
+    delay: D,
+    family: SensorFamily,  // AHT10 vs AHT20, picks the initialize opcode
+    state: SensorState,    // Track calibration state
+    crc_check: bool,       // Whether read() verifies the measurement frame's CRC-8 byte
+    max_poll_attempts: u8, // How many times to poll the busy bit before giving up
+    poll_interval_ms: u32, // Delay between busy-bit polls
 }
 
-impl<'a> AHT20<'a> {
-    /// Creates a new AHT20 driver.
-    /// It's recommended to call `init()` after this.
-    pub fn new(i2c: &'a RefCell<I2c<'static, Async>>) -> Self {
+impl<I2C, D> AHT20<I2C, D>
+where
+    I2C: I2c,
+    D: DelayNs,
+{
+    /// Creates a new driver from any `embedded-hal-async` I2C bus and delay provider, for the
+    /// given sensor family. It's recommended to call `init()` after this.
+    pub fn new(i2c: I2C, delay: D, family: SensorFamily) -> Self {
         AHT20 {
             i2c,
-            initialized: false,
+            delay,
+            family,
+            state: SensorState::Uninitialized,
+            crc_check: false,
+            max_poll_attempts: DEFAULT_MAX_POLL_ATTEMPTS,
+            poll_interval_ms: DEFAULT_POLL_INTERVAL_MS,
         }
     }
 
-    /// Initializes the AHT20 sensor.
-    /// This should be called once after creating the sensor instance or after a soft reset.
-    /// It ensures the sensor is calibrated.
-    pub async fn init(&mut self) -> Result<(), Aht20Error> {
-        // A small delay after power-on if this is the very first communication
-        // Timer::after(Duration::from_millis(DELAY_POWER_ON_MS)).await; // Often handled by system startup
+    /// Creates a new driver with custom busy-bit polling parameters, for callers that want to
+    /// trade responsiveness for robustness (or vice versa).
+    pub fn new_with_polling(
+        i2c: I2C,
+        delay: D,
+        family: SensorFamily,
+        max_poll_attempts: u8,
+        poll_interval_ms: u32,
+    ) -> Self {
+        AHT20 {
+            i2c,
+            delay,
+            family,
+            state: SensorState::Uninitialized,
+            crc_check: false,
+            max_poll_attempts,
+            poll_interval_ms,
+        }
+    }
+
+    /// Enables or disables CRC-8 verification of the 7-byte measurement frame in `read()`.
+    /// Disabled by default to match the original behavior.
+    pub fn set_crc_check(&mut self, enabled: bool) {
+        self.crc_check = enabled;
+    }
+
+    /// Returns the driver's current view of the sensor's calibration state.
+    pub fn state(&self) -> SensorState {
+        self.state
+    }
 
+    /// Reads the single status byte via the CheckStatus command (0x71).
+    ///
+    /// Unlike the 7-byte measurement frame, this response carries no trailing CRC byte, so
+    /// there is nothing here for `crc8()` to verify.
+    async fn read_status(&mut self) -> Result<u8, Aht20Error<I2C::Error>> {
         self.i2c
-            .borrow_mut()
-            .write(AHT20_ADDRESS, &CMD_INITIALIZE)
+            .write(AHT20_ADDRESS, &CMD_CHECK_STATUS)
             .await
             .map_err(|e| {
-                error!("AHT20: I2C Init Write Error: {:?}", e);
+                error!("AHT20: I2C CheckStatus Write Error");
                 Aht20Error::I2c(e)
             })?;
 
-        Timer::after(Duration::from_millis(DELAY_INIT_CALIBRATION_MS)).await;
-
-        // Verify initialization by reading status
         let mut status_byte = [0u8; 1];
         self.i2c
-            .borrow_mut()
             .read(AHT20_ADDRESS, &mut status_byte)
             .await
             .map_err(|e| {
-                error!("AHT20: I2C Read Status after Init Error: {:?}", e);
+                error!("AHT20: I2C CheckStatus Read Error");
+                Aht20Error::I2c(e)
+            })?;
+
+        Ok(status_byte[0])
+    }
+
+    /// Initializes the sensor (AHT10 or AHT20, per the `SensorFamily` passed to `new()`).
+    /// This should be called once after creating the sensor instance or after a soft reset.
+    /// It ensures the sensor is calibrated.
+    pub async fn init(&mut self) -> Result<(), Aht20Error<I2C::Error>> {
+        // The sensor may already be calibrated (e.g. it wasn't power-cycled), in which case
+        // there's no need to re-send the initialize command at all.
+        let status = self.read_status().await?;
+        if (status & 0x08) != 0 {
+            info!("AHT20: Already calibrated, skipping init command.");
+            self.state = SensorState::Calibrated;
+            return Ok(());
+        }
+
+        self.i2c
+            .write(AHT20_ADDRESS, &self.family.init_command())
+            .await
+            .map_err(|e| {
+                error!("AHT20: I2C Init Write Error");
                 Aht20Error::I2c(e)
             })?;
 
-        if (status_byte[0] & 0x08) == 0 {
+        // Poll the busy bit instead of blocking for a fixed window.
+        let status_byte;
+        let mut attempts = 0;
+        loop {
+            self.delay.delay_ms(self.poll_interval_ms).await;
+            let polled = self.read_status().await?;
+            if (polled & 0x80) == 0 {
+                status_byte = polled;
+                break;
+            }
+            attempts += 1;
+            if attempts >= self.max_poll_attempts {
+                error!("AHT20: Sensor still busy after init command, giving up.");
+                return Err(Aht20Error::MeasurementBusy);
+            }
+        }
+
+        if (status_byte & 0x08) == 0 {
             // Bit 3 is CalEnable
             error!("AHT20: Sensor failed to calibrate after init command.");
-            self.initialized = false;
+            self.state = SensorState::Faulted;
             return Err(Aht20Error::NotCalibrated);
         }
-        if (status_byte[0] & 0x80) != 0 {
-            // Bit 7 is Busy
-            error!("AHT20: Sensor busy after init command (unexpected).");
-            // This is unlikely but good to check
-        }
 
         info!("AHT20: Initialized and calibrated successfully.");
-        self.initialized = true;
+        self.state = SensorState::Calibrated;
         Ok(())
     }
 
-    /// Reads temperature (in Celsius) and relative humidity (in %).
-    /// Ensures the sensor is initialized before reading.
-    pub async fn read(&mut self) -> Result<(f32, f32), Aht20Error> {
-        if !self.initialized {
+    /// Writes the soft-reset command (0xBA), waits for the sensor to recover, and re-runs the
+    /// calibration check. Use this to recover a sensor that has reported a loss of calibration
+    /// without power-cycling it.
+    pub async fn soft_reset(&mut self) -> Result<(), Aht20Error<I2C::Error>> {
+        self.i2c
+            .write(AHT20_ADDRESS, &CMD_SOFT_RESET)
+            .await
+            .map_err(|e| {
+                error!("AHT20: I2C Soft Reset Error");
+                Aht20Error::I2c(e)
+            })?;
+        self.state = SensorState::Uninitialized;
+        self.delay.delay_ms(DELAY_SOFT_RESET_MS).await;
+        self.init().await
+    }
+
+    /// Reads a `Measurement` (temperature in Celsius and relative humidity in %).
+    /// Ensures the sensor is initialized before reading, and attempts a soft-reset recovery
+    /// if the sensor reports a loss of calibration mid-operation.
+    pub async fn read(&mut self) -> Result<Measurement, Aht20Error<I2C::Error>> {
+        match self.try_read().await {
+            Err(Aht20Error::NotCalibrated) => {
+                warn!("AHT20: Lost calibration mid-operation, attempting soft-reset recovery.");
+                self.soft_reset().await?;
+                self.try_read().await
+            }
+            other => other,
+        }
+    }
+
+    /// A single, non-recovering attempt at `read()`.
+    async fn try_read(&mut self) -> Result<Measurement, Aht20Error<I2C::Error>> {
+        if self.state != SensorState::Calibrated {
             warn!("AHT20: Sensor not initialized. Attempting to initialize now.");
             // Attempt to initialize if not already done.
             // Alternatively, return Aht20Error::NotInitialized and require user to call init().
@@ -109,55 +284,63 @@ impl<'a> AHT20<'a> {
 
         // Trigger measurement
         self.i2c
-            .borrow_mut()
             .write(AHT20_ADDRESS, &CMD_TRIGGER_MEASUREMENT)
             .await
             .map_err(|e| {
-                error!("AHT20: I2C Trigger Measurement Error: {:?}", e);
+                error!("AHT20: I2C Trigger Measurement Error");
                 Aht20Error::I2c(e)
             })?;
 
-        // Wait for the measurement to complete
-        Timer::after(Duration::from_millis(DELAY_MEASUREMENT_MS)).await;
-
-        // Read the sensor data (7 bytes: Status, H, H, H/T, T, T, CRC)
+        // Poll the busy bit instead of blocking for a fixed window; temperature-only reads
+        // are often ready well before the datasheet's worst-case 80ms.
         let mut data = [0u8; 7];
-        self.i2c
-            .borrow_mut()
-            .read(AHT20_ADDRESS, &mut data)
-            .await
-            .map_err(|e| {
-                error!("AHT20: I2C Read Data Error: {:?}", e);
+        let mut attempts = 0;
+        loop {
+            self.delay.delay_ms(self.poll_interval_ms).await;
+
+            // Read the sensor data (7 bytes: Status, H, H, H/T, T, T, CRC)
+            self.i2c.read(AHT20_ADDRESS, &mut data).await.map_err(|e| {
+                error!("AHT20: I2C Read Data Error");
                 Aht20Error::I2c(e)
             })?;
 
-        // Check status byte
-        // Bit 7 (Busy flag): 0 indicates measurement complete, 1 indicates busy.
-        if (data[0] & 0x80) != 0 {
-            error!(
-                "AHT20: Measurement data not ready (sensor busy). Status: {=u8:08b}",
-                data[0]
-            );
-            return Err(Aht20Error::MeasurementBusy);
+            if (data[0] & 0x80) == 0 {
+                break;
+            }
+            attempts += 1;
+            if attempts >= self.max_poll_attempts {
+                error!(
+                    "AHT20: Measurement data not ready after {} attempts (sensor busy). Status: {=u8:08b}",
+                    attempts, data[0]
+                );
+                return Err(Aht20Error::MeasurementBusy);
+            }
         }
+
         // Bit 3 (CalEnable): Should be 1 if calibrated.
         if (data[0] & 0x08) == 0 {
             warn!(
                 "AHT20: Sensor indicates not calibrated! Readings might be inaccurate. Status: {=u8:08b}",
                 data[0]
             );
-            // This could mean init was skipped or failed. Mark as uninitialized.
-            self.initialized = false;
+            // This could mean init was skipped or failed. Mark as faulted; read() answers this
+            // error with a soft-reset recovery and one more attempt.
+            self.state = SensorState::Faulted;
             return Err(Aht20Error::NotCalibrated);
         }
         // Other bits: Bit 6-5 Factory reserved (00), Bit 4 Reserved (0), Bit 2-0 Reserved (000)
         // Bit 0 is also related to CRC in some interpretations, but CRC is on byte 6.
 
+        // CRC: data[6], covers data[0..6]
+        if self.crc_check && crc8(&data[0..6]) != data[6] {
+            error!("AHT20: CRC mismatch on measurement frame.");
+            return Err(Aht20Error::CrcError);
+        }
+
         // Parse the data according to AHT20 datasheet
         // Status: data[0]
         // RH: data[1], data[2], data[3] bits 7:4
         // Temp: data[3] bits 3:0, data[4], data[5]
-        // CRC: data[6] (not currently checked)
 
         let raw_hum = ((data[1] as u32) << 12) | ((data[2] as u32) << 4) | ((data[3] as u32) >> 4);
 
@@ -171,21 +354,188 @@ impl<'a> AHT20<'a> {
         let humidity = (raw_hum as f32 / 1_048_576.0) * 100.0;
         let temperature = (raw_temp as f32 / 1_048_576.0) * 200.0 - 50.0;
 
-        Ok((temperature, humidity))
-    }
-
-    // Optional: Soft reset
-    // pub async fn soft_reset(&mut self) -> Result<(), Aht20Error> {
-    //     self.i2c
-    //         .borrow_mut()
-    //         .write(AHT20_ADDRESS, &CMD_SOFT_RESET)
-    //         .await
-    //         .map_err(|e| {
-    //             error!("AHT20: I2C Soft Reset Error: {:?}", e);
-    //             Aht20Error::I2c(e)
-    //         })?;
-    //     self.initialized = false; // Requires re-initialization
-    //     Timer::after(Duration::from_millis(DELAY_POWER_ON_MS)).await; // Sensor needs time after reset
-    //     Ok(())
-    // }
+        Ok(Measurement {
+            temperature_celsius: temperature,
+            humidity_percent: humidity,
+            raw_humidity: raw_hum,
+            raw_temperature: raw_temp,
+        })
+    }
+
+    /// Like `read()`, but always verifies the measurement frame's CRC-8 byte regardless of
+    /// the `crc_check` setting, returning `Aht20Error::CrcError` on mismatch.
+    pub async fn read_checked(&mut self) -> Result<Measurement, Aht20Error<I2C::Error>> {
+        let previous = self.crc_check;
+        self.crc_check = true;
+        let result = self.read().await;
+        self.crc_check = previous;
+        result
+    }
+}
+
+/// Convenience constructors for the default `embassy-time` delay, enabled by the `embassy`
+/// feature (on by default) so existing embassy users don't need to plumb their own `DelayNs`.
+#[cfg(feature = "embassy")]
+impl<I2C> AHT20<I2C, embassy_time::Delay>
+where
+    I2C: I2c,
+{
+    /// Creates a new driver backed by `embassy_time::Delay` for busy-bit polling.
+    pub fn new_embassy(i2c: I2C, family: SensorFamily) -> Self {
+        Self::new(i2c, embassy_time::Delay, family)
+    }
+
+    /// Like `new_embassy`, but with custom busy-bit polling parameters.
+    pub fn new_embassy_with_polling(
+        i2c: I2C,
+        family: SensorFamily,
+        max_poll_attempts: u8,
+        poll_interval_ms: u32,
+    ) -> Self {
+        Self::new_with_polling(
+            i2c,
+            embassy_time::Delay,
+            family,
+            max_poll_attempts,
+            poll_interval_ms,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc8_matches_known_vector() {
+        // Standard CRC-8 (poly 0x31, init 0xFF) test vector for bytes [0xBE, 0xEF].
+        assert_eq!(crc8(&[0xBE, 0xEF]), 0x92);
+    }
+
+    #[test]
+    fn crc8_of_empty_input_is_the_init_value() {
+        assert_eq!(crc8(&[]), 0xFF);
+    }
+
+    #[test]
+    fn crc8_of_single_zero_byte() {
+        assert_eq!(crc8(&[0x00]), 0xAC);
+    }
+
+    #[test]
+    fn temperature_fahrenheit_converts_from_celsius() {
+        let measurement = Measurement {
+            temperature_celsius: 25.0,
+            humidity_percent: 50.0,
+            raw_humidity: 0,
+            raw_temperature: 0,
+        };
+        assert_eq!(measurement.temperature_fahrenheit(), 77.0);
+    }
+
+    #[test]
+    fn dew_point_celsius_matches_magnus_formula() {
+        let measurement = Measurement {
+            temperature_celsius: 25.0,
+            humidity_percent: 50.0,
+            raw_humidity: 0,
+            raw_temperature: 0,
+        };
+        let dew_point = measurement.dew_point_celsius();
+        assert!(
+            (dew_point - 13.8516).abs() < 0.001,
+            "expected ~13.8516, got {}",
+            dew_point
+        );
+    }
+
+    #[test]
+    fn init_command_differs_between_sensor_families() {
+        assert_eq!(SensorFamily::Aht20.init_command(), CMD_INITIALIZE_AHT20);
+        assert_eq!(SensorFamily::Aht10.init_command(), CMD_INITIALIZE_AHT10);
+        assert_ne!(
+            SensorFamily::Aht10.init_command(),
+            SensorFamily::Aht20.init_command()
+        );
+    }
+}
+
+/// State-machine tests exercising `init()`/`read()`/`soft_reset()` against a mocked I2C bus,
+/// per the portability work in chunk0-3.
+#[cfg(test)]
+mod state_machine_tests {
+    use super::*;
+    use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+
+    /// A delay stub that returns immediately; these tests only care about transaction order,
+    /// not real timing.
+    struct NoopDelay;
+
+    impl DelayNs for NoopDelay {
+        async fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    #[test]
+    fn init_skips_init_command_when_already_calibrated() {
+        let mut i2c = I2cMock::new(&[
+            I2cTransaction::write(AHT20_ADDRESS, CMD_CHECK_STATUS.to_vec()),
+            I2cTransaction::read(AHT20_ADDRESS, vec![0x08]),
+        ]);
+        let mut sensor = AHT20::new(i2c.clone(), NoopDelay, SensorFamily::Aht20);
+
+        pollster::block_on(sensor.init()).unwrap();
+
+        assert_eq!(sensor.state(), SensorState::Calibrated);
+        i2c.done();
+    }
+
+    #[test]
+    fn init_sends_initialize_command_and_polls_until_calibrated() {
+        let mut i2c = I2cMock::new(&[
+            // First CheckStatus: not calibrated yet.
+            I2cTransaction::write(AHT20_ADDRESS, CMD_CHECK_STATUS.to_vec()),
+            I2cTransaction::read(AHT20_ADDRESS, vec![0x00]),
+            // Send the AHT20 initialize opcode.
+            I2cTransaction::write(AHT20_ADDRESS, CMD_INITIALIZE_AHT20.to_vec()),
+            // First poll: still busy.
+            I2cTransaction::write(AHT20_ADDRESS, CMD_CHECK_STATUS.to_vec()),
+            I2cTransaction::read(AHT20_ADDRESS, vec![0x80]),
+            // Second poll: calibrated and idle.
+            I2cTransaction::write(AHT20_ADDRESS, CMD_CHECK_STATUS.to_vec()),
+            I2cTransaction::read(AHT20_ADDRESS, vec![0x08]),
+        ]);
+        let mut sensor = AHT20::new(i2c.clone(), NoopDelay, SensorFamily::Aht20);
+
+        pollster::block_on(sensor.init()).unwrap();
+
+        assert_eq!(sensor.state(), SensorState::Calibrated);
+        i2c.done();
+    }
+
+    #[test]
+    fn read_recovers_via_soft_reset_when_calibration_is_lost_mid_read() {
+        let mut i2c = I2cMock::new(&[
+            // init(): already calibrated.
+            I2cTransaction::write(AHT20_ADDRESS, CMD_CHECK_STATUS.to_vec()),
+            I2cTransaction::read(AHT20_ADDRESS, vec![0x08]),
+            // Trigger measurement.
+            I2cTransaction::write(AHT20_ADDRESS, CMD_TRIGGER_MEASUREMENT.to_vec()),
+            // First poll: frame reports calibration lost (bit 3 clear).
+            I2cTransaction::read(AHT20_ADDRESS, vec![0x00, 0, 0, 0, 0, 0, 0]),
+            // soft_reset(): write 0xBA, then re-init (already calibrated again).
+            I2cTransaction::write(AHT20_ADDRESS, CMD_SOFT_RESET.to_vec()),
+            I2cTransaction::write(AHT20_ADDRESS, CMD_CHECK_STATUS.to_vec()),
+            I2cTransaction::read(AHT20_ADDRESS, vec![0x08]),
+            // Retry the measurement.
+            I2cTransaction::write(AHT20_ADDRESS, CMD_TRIGGER_MEASUREMENT.to_vec()),
+            I2cTransaction::read(AHT20_ADDRESS, vec![0x08, 0x19, 0x99, 0x90, 0x66, 0x66, 0x00]),
+        ]);
+        let mut sensor = AHT20::new(i2c.clone(), NoopDelay, SensorFamily::Aht20);
+
+        let measurement = pollster::block_on(sensor.read()).unwrap();
+
+        assert_eq!(sensor.state(), SensorState::Calibrated);
+        assert!(measurement.humidity_percent > 0.0);
+        i2c.done();
+    }
 }